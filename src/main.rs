@@ -0,0 +1,38 @@
+//! `clap-validator`'s command line entry point.
+
+mod plugin;
+mod util;
+
+use std::path::PathBuf;
+
+use plugin::probe::{self, PROBE_METADATA_SUBCOMMAND};
+
+fn main() {
+    // `--probe-metadata <path> <result-file>` must be handled before any other argument parsing:
+    // it's how `probe::probe_metadata()` re-execs this very binary to query a plugin library's
+    // metadata in an isolated child process, and it must never be allowed to fall through to the
+    // normal CLI.
+    let mut args = std::env::args_os().skip(1);
+    if let Some(arg) = args.next() {
+        if arg.to_str() == Some(PROBE_METADATA_SUBCOMMAND) {
+            let missing_argument = || {
+                eprintln!(
+                    "'{PROBE_METADATA_SUBCOMMAND}' requires a plugin path and a result file path."
+                );
+                std::process::exit(1);
+            };
+            let path = args.next().map(PathBuf::from).unwrap_or_else(missing_argument);
+            let result_file = args.next().map(PathBuf::from).unwrap_or_else(missing_argument);
+
+            probe::run_probe_metadata_subcommand(&path, &result_file);
+        }
+    }
+
+    run()
+}
+
+/// The validator's normal command line interface, entered once the hidden probing subcommand
+/// above has been ruled out.
+fn run() {
+    todo!("clap-validator's command line interface is not part of this change")
+}
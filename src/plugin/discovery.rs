@@ -0,0 +1,143 @@
+//! Discovery of CLAP plugin libraries in the platform's standard search locations, the same way a
+//! CLAP host scans for installed plugins.
+
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::library::{PluginLibrary, PluginLibraryMetadata};
+use super::metadata_cache::MetadataCache;
+use super::scan::{self, ScanOptions};
+
+impl PluginLibrary {
+    /// Scan the platform's standard CLAP search locations, plus any paths from the `CLAP_PATH`
+    /// environment variable, for `.clap` plugin libraries, loading each one serially and
+    /// in-process. A library that fails to load or report metadata does not abort the scan, its
+    /// error is simply recorded alongside its path. Paths are deduplicated by their canonicalized
+    /// form. Use [`discover_with()`][Self::discover_with()] to scan with a [`MetadataCache`],
+    /// parallelism, or out-of-process probing instead.
+    pub fn discover() -> Vec<(PathBuf, Result<PluginLibraryMetadata>)> {
+        PluginLibrary::discover_with(
+            None,
+            ScanOptions {
+                concurrency: 1,
+                out_of_process: false,
+            },
+        )
+    }
+
+    /// The same as [`discover()`][Self::discover()], but the discovered paths are fed through
+    /// [`scan::scan()`][scan::scan()] with `cache` and `options` instead of always being loaded
+    /// serially and in-process. This is how the cache (chunk0-2) and the out-of-process prober
+    /// (chunk0-3) actually get used for a directory scan, rather than only for a single path.
+    pub fn discover_with(
+        cache: Option<&MetadataCache>,
+        options: ScanOptions,
+    ) -> Vec<(PathBuf, Result<PluginLibraryMetadata>)> {
+        scan::scan(deduped_paths(), cache, options)
+    }
+}
+
+/// Find every `.clap` file or bundle under the platform's standard search locations (plus any
+/// `CLAP_PATH` entries), deduplicated by canonicalized path.
+fn deduped_paths() -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+
+    discover_paths()
+        .into_iter()
+        .filter(|path| {
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            seen.insert(canonical)
+        })
+        .collect()
+}
+
+/// Find every `.clap` file or bundle under the platform's standard search locations, with any
+/// `CLAP_PATH` entries searched first.
+fn discover_paths() -> Vec<PathBuf> {
+    let mut search_dirs: Vec<PathBuf> = Vec::new();
+    if let Some(clap_path) = std::env::var_os("CLAP_PATH") {
+        // `std::env::split_paths()` already splits on `:` on Unix and `;` on Windows.
+        search_dirs.extend(std::env::split_paths(&clap_path));
+    }
+    search_dirs.extend(standard_clap_locations());
+
+    let mut paths = Vec::new();
+    for dir in search_dirs {
+        find_clap_bundles(&dir, &mut paths);
+    }
+
+    paths
+}
+
+/// The platform's standard CLAP plugin search locations, in the order hosts are expected to search
+/// them. This does not include `CLAP_PATH`.
+fn standard_clap_locations() -> Vec<PathBuf> {
+    let mut locations = Vec::new();
+
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(home) = std::env::var_os("HOME") {
+            locations.push(PathBuf::from(home).join(".clap"));
+        }
+        locations.push(PathBuf::from("/usr/lib/clap"));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        if let Some(home) = std::env::var_os("HOME") {
+            locations.push(PathBuf::from(home).join("Library/Audio/Plug-Ins/CLAP"));
+        }
+        locations.push(PathBuf::from("/Library/Audio/Plug-Ins/CLAP"));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(common_program_files) = std::env::var_os("COMMONPROGRAMFILES") {
+            locations.push(PathBuf::from(common_program_files).join("CLAP"));
+        }
+        if let Some(local_app_data) = std::env::var_os("LOCALAPPDATA") {
+            locations.push(PathBuf::from(local_app_data).join("Programs/Common/CLAP"));
+        }
+    }
+
+    locations
+}
+
+/// Recursively scan `dir` for `.clap` files/bundles, appending any matches to `paths`. Missing or
+/// unreadable directories are silently skipped since most standard search locations won't exist on
+/// a given machine.
+fn find_clap_bundles(dir: &Path, paths: &mut Vec<PathBuf>) {
+    let mut visited_dirs = HashSet::new();
+    find_clap_bundles_inner(dir, paths, &mut visited_dirs);
+}
+
+/// The recursive part of [`find_clap_bundles()`]. `visited_dirs` tracks canonicalized directories
+/// already walked so that a symlink loop (or a self-referential mount) under one of the search
+/// locations can't send this into unbounded recursion.
+fn find_clap_bundles_inner(
+    dir: &Path,
+    paths: &mut Vec<PathBuf>,
+    visited_dirs: &mut HashSet<PathBuf>,
+) {
+    let canonical_dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    if !visited_dirs.insert(canonical_dir) {
+        return;
+    }
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // On macOS a `.clap` bundle is itself a directory, so it must be checked for before
+        // recursing into it.
+        if path.extension().is_some_and(|ext| ext == "clap") {
+            paths.push(path);
+        } else if path.is_dir() {
+            find_clap_bundles_inner(&path, paths, visited_dirs);
+        }
+    }
+}
@@ -2,15 +2,17 @@
 
 use anyhow::{Context, Result};
 use clap_sys::entry::clap_plugin_entry;
+use clap_sys::factory::draft::mini_curve_display::CLAP_MINI_CURVE_DISPLAY_FACTORY_ID;
+use clap_sys::factory::draft::plugin_state_converter::CLAP_PLUGIN_STATE_CONVERTER_FACTORY_ID;
 use clap_sys::factory::draft::preset_discovery::{
     clap_preset_discovery_factory, CLAP_PRESET_DISCOVERY_FACTORY_ID,
 };
 use clap_sys::factory::plugin_factory::{clap_plugin_factory, CLAP_PLUGIN_FACTORY_ID};
 use clap_sys::plugin::clap_plugin_descriptor;
 use clap_sys::version::clap_version;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::path::{Path, PathBuf};
 use std::ptr::NonNull;
 use std::sync::Arc;
@@ -33,16 +35,28 @@ pub struct PluginLibrary {
 }
 
 /// Metadata for a CLAP plugin library, which may contain multiple plugins.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PluginLibraryMetadata {
     pub version: (u32, u32, u32),
     pub plugins: Vec<PluginMetadata>,
+    /// Every factory this library advertises. See [`PluginLibrary::factories()`].
+    pub factories: Vec<SupportedFactory>,
+}
+
+/// A single factory advertised by a plugin library, as returned by
+/// [`PluginLibrary::factories()`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SupportedFactory {
+    /// The factory's ID, e.g. [`CLAP_PLUGIN_FACTORY_ID`]'s string representation.
+    pub id: String,
+    /// Whether this is part of the stable CLAP API, as opposed to one of the draft factories.
+    pub stable: bool,
 }
 
 /// Metadata for a single plugin within a CLAP plugin library. See
 /// [plugin.h](https://github.com/free-audio/clap/blob/main/include/clap/plugin.h) for a description
 /// of the fields.
-#[derive(Debug, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PluginMetadata {
     pub id: String,
     pub name: String,
@@ -182,6 +196,7 @@ impl PluginLibrary {
                 entry_point.clap_version.revision,
             ),
             plugins: Vec::new(),
+            factories: Vec::new(),
         };
         let num_plugins = unsafe_clap_call! { plugin_factory=>get_plugin_count(plugin_factory) };
         for i in 0..num_plugins {
@@ -209,6 +224,8 @@ impl PluginLibrary {
             anyhow::bail!("The plugin's factory contains multiple entries for the same plugin ID.");
         }
 
+        metadata.factories = self.factories();
+
         Ok(metadata)
     }
 
@@ -227,6 +244,35 @@ impl PluginLibrary {
         !factory_pointer.is_null()
     }
 
+    /// Probe every factory ID known to this crate (the plugin factory, the preset-discovery
+    /// factory, and draft factories such as the plugin-state-converter and mini-curve-display
+    /// factories) by calling `get_factory()` for each, and report which ones this library actually
+    /// supports. This gives a single view of everything a library advertises, rather than only the
+    /// plugin and preset-discovery factories [`metadata()`][Self::metadata()] and
+    /// [`preset_discovery_factory()`][Self::preset_discovery_factory()] special-case.
+    pub fn factories(&self) -> Vec<SupportedFactory> {
+        let entry_point = get_clap_entry_point(&self.library)
+            .expect("A Plugin was constructed for a plugin with no entry point");
+
+        KNOWN_FACTORY_IDS
+            .iter()
+            .filter_map(|&(id, stable)| {
+                let factory_pointer = unsafe_clap_call! { entry_point=>get_factory(id.as_ptr()) };
+                if factory_pointer.is_null() {
+                    None
+                } else {
+                    Some(SupportedFactory {
+                        id: id
+                            .to_str()
+                            .expect("Factory IDs are always valid UTF-8")
+                            .to_string(),
+                        stable,
+                    })
+                }
+            })
+            .collect()
+    }
+
     /// Try to create the plugin with the given ID, and using the provided host instance. The plugin
     /// IDs supported by this plugin library can be found by calling
     /// [`metadata()`][Self::metadata()]. The returned plugin has not yet been initialized, and
@@ -269,6 +315,15 @@ impl PluginLibrary {
     }
 }
 
+/// Every factory ID [`PluginLibrary::factories()`] knows how to probe for, and whether it's part
+/// of the stable CLAP API or one of the draft factories.
+const KNOWN_FACTORY_IDS: &[(&CStr, bool)] = &[
+    (CLAP_PLUGIN_FACTORY_ID, true),
+    (CLAP_PRESET_DISCOVERY_FACTORY_ID, false),
+    (CLAP_PLUGIN_STATE_CONVERTER_FACTORY_ID, false),
+    (CLAP_MINI_CURVE_DISPLAY_FACTORY_ID, false),
+];
+
 impl PluginLibraryMetadata {
     /// Get the CLAP version representation for this plugin library.
     pub fn clap_version(&self) -> clap_version {
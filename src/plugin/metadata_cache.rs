@@ -0,0 +1,267 @@
+//! A persistent on-disk cache of plugin library metadata, so that scanning a large CLAP install
+//! doesn't need to load (and risk crashing on) every library each time.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use super::library::{PluginLibrary, PluginLibraryMetadata};
+
+/// A single cached metadata entry. It's only valid as long as the plugin library's file size and
+/// modification time haven't changed since it was recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    modified: Option<SystemTime>,
+    metadata: PluginLibraryMetadata,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheData {
+    /// Keyed by the plugin library's absolute path.
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+/// A persistent cache of [`PluginLibraryMetadata`], used by
+/// [`PluginLibrary::metadata_cached()`][PluginLibrary::metadata_cached()] to avoid loading a
+/// plugin library's shared object just to read its descriptors. Entries are keyed by path, file
+/// size, and modification time: if none of those have changed since an entry was written, the
+/// library isn't loaded at all.
+#[derive(Debug)]
+pub struct MetadataCache {
+    cache_file: PathBuf,
+    data: Mutex<CacheData>,
+    /// When set, [`lookup()`][Self::lookup()] always reports a miss, forcing every library to be
+    /// reloaded and its cache entry refreshed.
+    pub force_rescan: bool,
+}
+
+impl MetadataCache {
+    /// Load the metadata cache from the user's cache directory. Returns an empty cache if no
+    /// cache file exists yet or if the existing one could not be parsed.
+    pub fn load(force_rescan: bool) -> Result<MetadataCache> {
+        let cache_file = cache_file_path().context("Could not locate the metadata cache file")?;
+        let data = std::fs::read_to_string(&cache_file)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        Ok(MetadataCache {
+            cache_file,
+            data: Mutex::new(data),
+            force_rescan,
+        })
+    }
+
+    /// Write the cache back to disk so future scans can benefit from the entries gathered during
+    /// this one.
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.cache_file.parent() {
+            std::fs::create_dir_all(parent)
+                .context("Could not create the metadata cache's parent directory")?;
+        }
+
+        let data = self.data.lock().unwrap();
+        let contents =
+            serde_json::to_string(&*data).context("Could not serialize the metadata cache")?;
+        std::fs::write(&self.cache_file, contents)
+            .context("Could not write the metadata cache to disk")?;
+
+        Ok(())
+    }
+
+    /// Look up cached metadata for `path` without loading it. Returns `None` on a cache miss, if
+    /// `force_rescan` is set, or if `path`'s current file size/modification time no longer match
+    /// the cached entry, in which case the caller is expected to load the library itself and
+    /// [`insert()`][Self::insert()] a fresh entry.
+    pub fn lookup(&self, path: &Path) -> Option<PluginLibraryMetadata> {
+        if self.force_rescan {
+            return None;
+        }
+
+        let path = &absolute_path(path);
+        let file_metadata = std::fs::metadata(path).ok()?;
+        let size = file_metadata.len();
+        let modified = file_metadata.modified().ok();
+
+        let data = self.data.lock().unwrap();
+        let entry = data.entries.get(path)?;
+        (entry.size == size && entry.modified == modified).then(|| entry.metadata.clone())
+    }
+
+    /// Insert or replace the cache entry for `path`, stamping it with `path`'s current file size
+    /// and modification time.
+    pub fn insert(&self, path: &Path, metadata: PluginLibraryMetadata) -> Result<()> {
+        let path = &absolute_path(path);
+        let file_metadata = std::fs::metadata(path)
+            .context("Could not read the plugin library's file metadata")?;
+        let size = file_metadata.len();
+        let modified = file_metadata.modified().ok();
+
+        let mut data = self.data.lock().unwrap();
+        data.entries.insert(
+            path.to_path_buf(),
+            CacheEntry {
+                size,
+                modified,
+                metadata,
+            },
+        );
+
+        Ok(())
+    }
+}
+
+/// `path` made absolute by joining it to the current directory, so cache entries are keyed the same
+/// way regardless of how the library's path was spelled.
+fn absolute_path(path: &Path) -> PathBuf {
+    std::env::current_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join(path)
+}
+
+/// The path to the metadata cache file under the user's cache directory.
+fn cache_file_path() -> Option<PathBuf> {
+    Some(
+        user_cache_dir()?
+            .join("clap-validator")
+            .join("metadata-cache.json"),
+    )
+}
+
+/// The platform's user cache directory, e.g. `~/.cache` on Linux, `~/Library/Caches` on macOS, or
+/// `%LOCALAPPDATA%` on Windows. Returns `None` on any other target, since there's no standard
+/// location to fall back to.
+fn user_cache_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(xdg_cache_home) = std::env::var_os("XDG_CACHE_HOME") {
+            return Some(PathBuf::from(xdg_cache_home));
+        }
+        return std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache"));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        return std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Caches"));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        return std::env::var_os("LOCALAPPDATA").map(PathBuf::from);
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        None
+    }
+}
+
+impl PluginLibrary {
+    /// The same as [`load()`][Self::load()] followed by [`metadata()`][Self::metadata()], but
+    /// backed by `cache`: if `path`'s size and modification time still match a cached entry, the
+    /// cached metadata is returned directly and the library is never loaded at all. Otherwise the
+    /// library is loaded, queried, and the cache entry is refreshed.
+    pub fn metadata_cached(
+        path: impl AsRef<Path>,
+        cache: &MetadataCache,
+    ) -> Result<PluginLibraryMetadata> {
+        let path = path.as_ref();
+
+        if let Some(cached) = cache.lookup(path) {
+            return Ok(cached);
+        }
+
+        let metadata = PluginLibrary::load(path)?.metadata()?;
+        cache.insert(path, metadata.clone())?;
+
+        Ok(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_cache() -> MetadataCache {
+        MetadataCache {
+            cache_file: std::env::temp_dir().join("clap-validator-metadata-cache-test.json"),
+            data: Mutex::new(CacheData::default()),
+            force_rescan: false,
+        }
+    }
+
+    fn sample_metadata() -> PluginLibraryMetadata {
+        PluginLibraryMetadata {
+            version: (1, 0, 0),
+            plugins: Vec::new(),
+            factories: Vec::new(),
+        }
+    }
+
+    /// Create a uniquely named file under the system's temporary directory with `contents`,
+    /// returning its path. The caller is responsible for removing it.
+    fn temp_file_with_contents(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "clap-validator-metadata-cache-test-{name}-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        std::fs::write(&path, contents).expect("Could not write the temporary test file");
+        path
+    }
+
+    #[test]
+    fn lookup_misses_for_a_path_with_no_entry() {
+        let cache = empty_cache();
+        let path = temp_file_with_contents("unseen", b"abc");
+
+        assert_eq!(cache.lookup(&path), None);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn insert_then_lookup_round_trips_while_the_file_is_unchanged() {
+        let cache = empty_cache();
+        let path = temp_file_with_contents("round-trip", b"abc");
+        let metadata = sample_metadata();
+
+        cache.insert(&path, metadata.clone()).unwrap();
+
+        assert_eq!(cache.lookup(&path), Some(metadata));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn lookup_misses_once_the_files_size_changes() {
+        let cache = empty_cache();
+        let path = temp_file_with_contents("size-change", b"abc");
+
+        cache.insert(&path, sample_metadata()).unwrap();
+        // Changing the file's length changes its cached size, invalidating the entry even if the
+        // modification time ends up identical (e.g. on a coarse filesystem clock).
+        std::fs::write(&path, b"a substantially longer replacement").unwrap();
+
+        assert_eq!(cache.lookup(&path), None);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn force_rescan_ignores_an_otherwise_valid_entry() {
+        let mut cache = empty_cache();
+        let path = temp_file_with_contents("force-rescan", b"abc");
+
+        cache.insert(&path, sample_metadata()).unwrap();
+        cache.force_rescan = true;
+
+        assert_eq!(cache.lookup(&path), None);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,12 @@
+//! Abstractions for loading and interacting with CLAP plugins.
+
+pub mod discovery;
+pub mod host;
+pub mod instance;
+pub mod library;
+pub mod metadata_cache;
+pub mod preset_discovery;
+pub mod probe;
+pub mod scan;
+
+pub use library::{PluginLibrary, PluginLibraryMetadata, PluginMetadata};
@@ -0,0 +1,104 @@
+//! Out-of-process metadata probing, so that a single malformed plugin library can't crash the
+//! whole validator while scanning a directory of installed plugins.
+//!
+//! [`load()`][super::library::PluginLibrary::load()] runs the plugin's `clap_entry::init()` and,
+//! by extension, [`metadata()`][super::library::PluginLibrary::metadata()] runs
+//! `get_plugin_descriptor()` in the current process. A plugin that segfaults or aborts during
+//! either call takes the validator down with it. [`probe_metadata()`] instead re-execs the
+//! validator binary as a metadata-probing worker, so a crashing library only takes down the
+//! child.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::library::{PluginLibrary, PluginLibraryMetadata};
+
+/// The hidden subcommand used to re-exec the validator binary as a metadata-probing worker. The
+/// binary's `main()` must check for this as its first argument, before any normal argument
+/// parsing, and dispatch to [`run_probe_metadata_subcommand()`] when it's present.
+pub const PROBE_METADATA_SUBCOMMAND: &str = "--probe-metadata";
+
+/// Query `path`'s metadata in a child process instead of the current one. If the child crashes or
+/// exits abnormally while loading the library or reading its descriptors, this returns a
+/// structured error instead of taking down the calling process.
+///
+/// The metadata is handed back through a dedicated temporary file rather than the child's
+/// stdout: `clap_entry::init()` and `get_plugin_descriptor()` run untrusted plugin code, which is
+/// free to write its own logging to stdout, and sharing that stream with the structured result
+/// would make a perfectly good probe indistinguishable from a corrupted one.
+pub fn probe_metadata(path: &Path) -> Result<PluginLibraryMetadata> {
+    let current_exe = std::env::current_exe()
+        .context("Could not determine the path to the clap-validator executable")?;
+    let result_file = temporary_result_file_path();
+
+    let output = Command::new(current_exe)
+        .arg(PROBE_METADATA_SUBCOMMAND)
+        .arg(path)
+        .arg(&result_file)
+        .stdin(Stdio::null())
+        .output()
+        .context("Could not spawn the metadata probing child process")?;
+
+    // The child may have exited before writing (or may never have been able to write) the result
+    // file, so this is cleaned up unconditionally rather than only on the success path.
+    let contents = std::fs::read(&result_file);
+    let _ = std::fs::remove_file(&result_file);
+
+    if !output.status.success() {
+        let reason = match output.status.code() {
+            Some(code) => format!("exited with status code {code}"),
+            // `ExitStatus::code()` returns `None` when the process was terminated by a signal.
+            None => "was terminated by a signal".to_string(),
+        };
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(
+            "The metadata probing child process for '{}' {reason}. stderr: {stderr}",
+            path.display()
+        );
+    }
+
+    let contents =
+        contents.context("The metadata probing child process did not write a result file")?;
+    serde_json::from_slice(&contents)
+        .context("Could not parse the metadata probing child process's result file")
+}
+
+/// Run the hidden `--probe-metadata <path> <result-file>` subcommand: load `path` as a plugin
+/// library, write its metadata to `result_file` as JSON, and exit with a status code reflecting
+/// whether that succeeded. This is the child-process half of [`probe_metadata()`] and never
+/// returns.
+pub fn run_probe_metadata_subcommand(path: &Path, result_file: &Path) -> ! {
+    let result = PluginLibrary::load(path).and_then(|library| library.metadata());
+    match result {
+        Ok(metadata) => {
+            // `PluginLibraryMetadata` is always serializable, so this can't realistically fail.
+            let json = serde_json::to_string(&metadata)
+                .expect("Could not serialize the plugin library's metadata");
+            match std::fs::write(result_file, json) {
+                Ok(()) => std::process::exit(0),
+                Err(err) => {
+                    eprintln!("Could not write the probe result file: {err:#}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Err(err) => {
+            eprintln!("{err:#}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// A unique path under the system's temporary directory for a single [`probe_metadata()`] call's
+/// result file.
+fn temporary_result_file_path() -> PathBuf {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+
+    std::env::temp_dir().join(format!(
+        "clap-validator-probe-{}-{id}.json",
+        std::process::id()
+    ))
+}
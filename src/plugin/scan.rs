@@ -0,0 +1,223 @@
+//! Parallel scanning of discovered CLAP plugin libraries across a bounded pool of worker threads.
+
+use anyhow::Result;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+use super::library::{PluginLibrary, PluginLibraryMetadata};
+use super::metadata_cache::MetadataCache;
+use super::probe::probe_metadata;
+
+/// Options controlling how [`scan()`] loads each discovered library.
+#[derive(Debug, Clone, Copy)]
+pub struct ScanOptions {
+    /// How many libraries to load concurrently. Defaults to
+    /// [`std::thread::available_parallelism()`], falling back to `1` if that can't be determined.
+    pub concurrency: usize,
+    /// Load each library's metadata in its own child process using [`probe_metadata()`], so a
+    /// crashing library only takes down its worker instead of the whole scan.
+    pub out_of_process: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            concurrency: thread::available_parallelism().map_or(1, |n| n.get()),
+            out_of_process: false,
+        }
+    }
+}
+
+/// Load metadata for every path in `paths` across a bounded pool of worker threads, returning a
+/// deterministic, path-sorted report. Because [`libloading::Library`] init/deinit is per-library
+/// and [`PluginLibrary`] owns its own handle, each worker can load and drop libraries
+/// independently of the others. A panic while probing one library is caught and recorded as an
+/// error for that path; it never poisons the results of the other workers.
+pub fn scan(
+    paths: Vec<PathBuf>,
+    cache: Option<&MetadataCache>,
+    options: ScanOptions,
+) -> Vec<(PathBuf, Result<PluginLibraryMetadata>)> {
+    let results = scan_with(paths, options.concurrency, |path| {
+        load_metadata(path, cache, options.out_of_process)
+    });
+
+    // Persist whatever entries were refreshed during this scan so a later run can benefit from
+    // them too. A failure to save is not reflected in `results`, since it doesn't change any
+    // individual library's outcome; it's only reported to stderr.
+    if let Some(cache) = cache {
+        if let Err(err) = cache.save() {
+            eprintln!("Could not save the metadata cache: {err:#}");
+        }
+    }
+
+    results
+}
+
+/// The bounded worker-pool core of [`scan()`], generic over how a single path's metadata is
+/// loaded so it can be exercised with a fake `load` in tests without a real plugin library.
+fn scan_with(
+    paths: Vec<PathBuf>,
+    concurrency: usize,
+    load: impl Fn(&Path) -> Result<PluginLibraryMetadata> + Sync,
+) -> Vec<(PathBuf, Result<PluginLibraryMetadata>)> {
+    let concurrency = concurrency.max(1);
+
+    let (path_tx, path_rx) = mpsc::channel::<PathBuf>();
+    for path in paths {
+        path_tx
+            .send(path)
+            .expect("The path channel's receiver should still be alive");
+    }
+    drop(path_tx);
+    let path_rx = Mutex::new(path_rx);
+
+    let (result_tx, result_rx) = mpsc::channel();
+    let load = &load;
+
+    thread::scope(|scope| {
+        for _ in 0..concurrency {
+            let path_rx = &path_rx;
+            let result_tx = result_tx.clone();
+
+            scope.spawn(move || loop {
+                let path = match path_rx.lock().unwrap().recv() {
+                    Ok(path) => path,
+                    Err(_) => break,
+                };
+
+                let metadata = panic::catch_unwind(AssertUnwindSafe(|| load(&path)))
+                    .unwrap_or_else(|panic| {
+                        Err(anyhow::anyhow!(
+                            "A worker thread panicked while probing '{}': {}",
+                            path.display(),
+                            util::panic_payload_to_string(panic)
+                        ))
+                    });
+
+                // The receiving end only goes away once `scan_with()` itself returns, so every
+                // worker is guaranteed to still be able to report its result.
+                let _ = result_tx.send((path, metadata));
+            });
+        }
+    });
+    drop(result_tx);
+
+    let mut results: Vec<_> = result_rx.into_iter().collect();
+    results.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    results
+}
+
+/// Load a single library's metadata, going through the metadata cache and/or the out-of-process
+/// prober depending on `cache` and `out_of_process`. The cache is consulted before the library is loaded (or probed)
+/// at all, and is refreshed on a cache miss regardless of which way the metadata was obtained.
+fn load_metadata(
+    path: &Path,
+    cache: Option<&MetadataCache>,
+    out_of_process: bool,
+) -> Result<PluginLibraryMetadata> {
+    if let Some(cache) = cache {
+        if let Some(cached) = cache.lookup(path) {
+            return Ok(cached);
+        }
+    }
+
+    let metadata = if out_of_process {
+        probe_metadata(path)?
+    } else {
+        PluginLibrary::load(path)?.metadata()?
+    };
+
+    if let Some(cache) = cache {
+        cache.insert(path, metadata.clone())?;
+    }
+
+    Ok(metadata)
+}
+
+mod util {
+    use std::any::Any;
+
+    /// Turn a caught panic payload into a human-readable message, falling back to a generic
+    /// description for payloads that aren't a `&str` or `String`.
+    pub fn panic_payload_to_string(payload: Box<dyn Any + Send>) -> String {
+        if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "<non-string panic payload>".to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::library::SupportedFactory;
+
+    fn metadata_for(path: &Path) -> PluginLibraryMetadata {
+        PluginLibraryMetadata {
+            version: (1, 0, 0),
+            plugins: Vec::new(),
+            factories: vec![SupportedFactory {
+                id: path.display().to_string(),
+                stable: true,
+            }],
+        }
+    }
+
+    #[test]
+    fn results_are_sorted_by_path_regardless_of_completion_order() {
+        let paths: Vec<PathBuf> = ["c", "a", "b"].iter().map(PathBuf::from).collect();
+
+        // Slower paths are handed out first so that, without the final sort, they would finish
+        // last.
+        let results = scan_with(paths, 4, |path| {
+            if path == Path::new("c") {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            Ok(metadata_for(path))
+        });
+
+        let sorted_paths: Vec<&Path> = results.iter().map(|(path, _)| path.as_path()).collect();
+        assert_eq!(sorted_paths, vec![Path::new("a"), Path::new("b"), Path::new("c")]);
+    }
+
+    #[test]
+    fn a_panicking_worker_reports_an_error_instead_of_crashing() {
+        let paths: Vec<PathBuf> = ["good", "bad"].iter().map(PathBuf::from).collect();
+
+        let results = scan_with(paths, 2, |path| {
+            if path == Path::new("bad") {
+                panic!("simulated crash while probing a plugin");
+            }
+            Ok(metadata_for(path))
+        });
+
+        let good = results
+            .iter()
+            .find(|(path, _)| path == Path::new("good"))
+            .expect("the 'good' path should still have a result");
+        assert!(good.1.is_ok());
+
+        let bad = results
+            .iter()
+            .find(|(path, _)| path == Path::new("bad"))
+            .expect("the 'bad' path should still have a result");
+        assert!(bad.1.is_err());
+    }
+
+    #[test]
+    fn every_path_gets_a_result_even_with_more_workers_than_paths() {
+        let paths: Vec<PathBuf> = ["only"].iter().map(PathBuf::from).collect();
+
+        let results = scan_with(paths, 8, |path| Ok(metadata_for(path)));
+
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.is_ok());
+    }
+}